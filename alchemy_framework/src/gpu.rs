@@ -1,34 +1,60 @@
 use crate::camera::GPUObject;
+use crate::instance::{Instance, InstanceRaw};
+use crate::light::Light;
+use crate::mesh::{MeshHandle, MeshPool, Vertex};
+use crate::render_pass::{PassTargets, RenderPass};
 use crate::texture;
+use crate::viewport::{SwapChainViewport, ViewportImage};
+use anyhow::{Context, Result};
+use cgmath::SquareMatrix;
+use rayon::prelude::*;
 use std::iter;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
 use winit::window::Window;
 #[allow(unused_imports)]
 use log::{error, warn, info, debug, trace};
 
-const RENDERFORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
 const SCUSAGE: wgpu::TextureUsage = wgpu::TextureUsage::RENDER_ATTACHMENT;
-const SCPRESENT: wgpu::PresentMode = wgpu::PresentMode::Fifo;
+
+/// Runtime configuration for `State::new`. Defaults to `PRIMARY` so the
+/// app isn't pinned to Vulkan and left unable to find an adapter on
+/// platforms that only expose Metal or DX12.
+pub struct StateConfig {
+    pub backends: wgpu::BackendBit,
+    pub present_mode: wgpu::PresentMode,
+    pub power_preference: wgpu::PowerPreference,
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::BackendBit::PRIMARY,
+            present_mode: wgpu::PresentMode::Fifo,
+            power_preference: wgpu::PowerPreference::default(),
+        }
+    }
+}
 
 pub struct State {
     surface: wgpu::Surface,
-    pub device: wgpu::Device,
+    pub device: Arc<wgpu::Device>,
     pub queue: wgpu::Queue,
-    pub sc_desc: wgpu::SwapChainDescriptor,
-    swap_chain: wgpu::SwapChain,
     pub size: winit::dpi::PhysicalSize<u32>,
-    depth_texture: texture::Texture,
-    effect: Option<BasicEffect>, //This is initialized later.
+    render_passes: Vec<Box<dyn RenderPass>>,
+    debug_depth_pass: Option<DepthEffect>,
+    /// Opt-in via `set_parallel_recording`: records each registered pass
+    /// on its own `CommandEncoder` across a rayon thread pool instead of
+    /// one shared encoder, then submits all the resulting command
+    /// buffers together. See `render` for the ordering guarantee this
+    /// still provides.
+    parallel_recording: bool,
 }
 
 impl State {
-    pub async fn new(window: &Window) -> Self {
+    pub async fn new(window: &Window, config: StateConfig) -> Result<(Self, SwapChainViewport)> {
         let size = window.inner_size();
-        let instance = wgpu::Instance::new(wgpu::BackendBit::VULKAN);
-        let surface = unsafe { instance.create_surface(window) };
-        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::default(),
-            compatible_surface: Some(&surface),
-        }).await.unwrap();
+        let (surface, adapter) = Self::request_surface_and_adapter(window, &config).await?;
 
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
@@ -37,106 +63,191 @@ impl State {
                 limits: wgpu::Limits::default(),
             },
             None, // Trace path
-        ).await.unwrap();
+        ).await.context("failed to request a device from the adapter")?;
 
-        let sc_desc = wgpu::SwapChainDescriptor { usage: SCUSAGE, format: RENDERFORMAT, width: size.width, height: size.height, present_mode: SCPRESENT};
+        let format = surface.get_swap_chain_preferred_format(&adapter)
+            .context("the surface is not compatible with the selected adapter")?;
 
-        let swap_chain = device.create_swap_chain(&surface, &sc_desc);
-        let depth_texture = texture::Texture::create_depth_texture(&device, &sc_desc, "depth_texture");
+        let sc_desc = wgpu::SwapChainDescriptor { usage: SCUSAGE, format, width: size.width, height: size.height, present_mode: config.present_mode };
+        let viewport = SwapChainViewport::new(&device, &surface, sc_desc);
 
-        Self {
+        let state = Self {
             surface,
-            device,
+            device: Arc::new(device),
             queue,
-            sc_desc,
-            swap_chain,
             size,
-            depth_texture,
-            effect: None,
+            render_passes: Vec::new(),
+            debug_depth_pass: None,
+            parallel_recording: false,
+        };
+
+        Ok((state, viewport))
+    }
+
+    /// Tries each concrete backend in `config.backends` in turn (rather
+    /// than handing the whole bitmask to a single `request_adapter`
+    /// call, which only succeeds if wgpu picks a working backend on the
+    /// first try), so e.g. the default `PRIMARY` still finds a usable
+    /// adapter on a Metal-only machine after Vulkan fails. Backends not
+    /// in `config.backends` are never tried.
+    async fn request_surface_and_adapter(window: &Window, config: &StateConfig) -> Result<(wgpu::Surface, wgpu::Adapter)> {
+        const CANDIDATE_BACKENDS: &[wgpu::BackendBit] = &[
+            wgpu::BackendBit::VULKAN,
+            wgpu::BackendBit::METAL,
+            wgpu::BackendBit::DX12,
+            wgpu::BackendBit::DX11,
+            wgpu::BackendBit::GL,
+        ];
+
+        for &backend in CANDIDATE_BACKENDS.iter().filter(|backend| config.backends.contains(**backend)) {
+            let instance = wgpu::Instance::new(backend);
+            let surface = unsafe { instance.create_surface(window) };
+            let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: config.power_preference,
+                compatible_surface: Some(&surface),
+            }).await;
+
+            match adapter {
+                Some(adapter) => return Ok((surface, adapter)),
+                None => debug!("no adapter found for backend {:?}, trying the next one", backend),
+            }
         }
+
+        Err(anyhow::anyhow!(
+            "no graphics adapter compatible with any of the requested backend(s) ({:?}) was found",
+            config.backends
+        ))
     }
 
-    pub fn add_effect(&mut self, effect: BasicEffect){ 
-        self.effect = Some(effect);
+    pub fn add_pass(&mut self, pass: impl RenderPass + 'static){
+        self.render_passes.push(Box::new(pass));
     }
 
-    pub fn get_effect(&self) -> &BasicEffect{
-        return self.effect.as_ref().unwrap();
+    /// Toggles drawing the depth-visualization overlay after the
+    /// regular render passes, for debugging shadow maps and the depth
+    /// prepass. Pass `None` to turn it off.
+    pub fn set_debug_depth_pass(&mut self, pass: Option<DepthEffect>){
+        self.debug_depth_pass = pass;
     }
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+    /// Enables or disables recording the registered passes on separate
+    /// encoders across a rayon thread pool. See `render` for what this
+    /// does and does not guarantee about execution order.
+    pub fn set_parallel_recording(&mut self, enabled: bool){
+        self.parallel_recording = enabled;
+    }
+
+    /// Resizes the swap chain and its depth buffer, then rebuilds the
+    /// debug depth pass (if enabled) against the new depth texture so it
+    /// can't be left pointing at the one `viewport.resize` just replaced.
+    pub fn resize(&mut self, viewport: &mut SwapChainViewport, new_size: winit::dpi::PhysicalSize<u32>) {
         self.size = new_size;
-        self.sc_desc.width = new_size.width;
-        self.sc_desc.height = new_size.height;
-        self.swap_chain = self.device.create_swap_chain(&self.surface, &self.sc_desc);
-        self.depth_texture = texture::Texture::create_depth_texture(&self.device, &self.sc_desc, "depth_texture");
+        viewport.resize(&self.device, &self.surface, new_size.width, new_size.height);
+
+        if let Some(mut debug_pass) = self.debug_depth_pass.take() {
+            debug_pass.rebuild(&*self, &*viewport);
+            self.debug_depth_pass = Some(debug_pass);
+        }
+    }
+
+    /// Changes the present mode at runtime and recreates the swap chain
+    /// to pick it up, mirroring `resize`. `viewport.present_mode()` is
+    /// the authoritative value afterward; `State` keeps no copy of it.
+    pub fn set_present_mode(&mut self, viewport: &mut SwapChainViewport, present_mode: wgpu::PresentMode) {
+        viewport.set_present_mode(&self.device, &self.surface, present_mode);
     }
 
     pub fn write_buffer(&mut self, buffer: &wgpu::Buffer, bytes: impl bytemuck::Pod ){
         self.queue.write_buffer(&buffer, 0, bytemuck::cast_slice(&[bytes]));
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SwapChainError> {
-        let frame = self.swap_chain.get_current_frame()?.output;
-
-        let mut encoder = self.device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Render Encoder"),});
-
-        {
-            let mut render_pass = encoder
-            .begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &frame.view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: true,
-                    },
-                }],
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
-                    attachment: &self.depth_texture.view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: true,
-                    }),
-                    stencil_ops: None,
-                }),
-            });
-            
-            match &self.effect{
-                Some(effect) => {
-                    effect.render(&mut render_pass);
-                },
-                None => panic!("The Render pipeline was not initialized, please include init_pipleine somehwere in the code"),
+    /// Records the registered passes, either serially onto one shared
+    /// encoder or, with `parallel_recording` enabled, each on its own
+    /// encoder built across a rayon thread pool and submitted together.
+    ///
+    /// Frame-in-flight ordering guarantee: recording happens out of
+    /// order in parallel mode, but submission to `self.queue` always
+    /// follows registration order (`add_pass` order, then the debug
+    /// depth pass last), and wgpu executes submitted command buffers in
+    /// submission order. So passes that must run sequentially — a depth
+    /// prepass before the color pass that reads its depth buffer, say —
+    /// are only safe to split across two registered passes if nothing
+    /// about their relative *recording* order matters; `BasicEffect`
+    /// instead keeps its own prepass and color pass inside a single
+    /// `record` call for exactly this reason.
+    pub fn render(&self, viewport: &mut impl ViewportImage) -> Result<(), wgpu::SwapChainError> {
+        let acquired = viewport.acquire()?;
+
+        let targets = PassTargets {
+            frame_view: acquired.frame.view(),
+            depth_view: acquired.depth_texture,
+        };
+
+        if self.parallel_recording {
+            let device = &self.device;
+            let mut command_buffers: Vec<wgpu::CommandBuffer> = self.render_passes
+                .par_iter()
+                .map(|pass| {
+                    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Parallel Render Encoder") });
+                    pass.record(&mut encoder, &targets);
+                    encoder.finish()
+                })
+                .collect();
+
+            if let Some(debug_pass) = &self.debug_depth_pass {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Debug Depth Encoder") });
+                debug_pass.record(&mut encoder, &targets);
+                command_buffers.push(encoder.finish());
             }
-        }
 
-        self.queue.submit(iter::once(encoder.finish()));
+            self.queue.submit(command_buffers);
+        } else {
+            let mut encoder = self.device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Render Encoder"),});
+
+            for pass in &self.render_passes {
+                pass.record(&mut encoder, &targets);
+            }
+
+            if let Some(debug_pass) = &self.debug_depth_pass {
+                debug_pass.record(&mut encoder, &targets);
+            }
+
+            self.queue.submit(iter::once(encoder.finish()));
+        }
 
         Ok(())
     }
 }
 
+/// The pipelines used once a depth prepass is enabled: a depth-only
+/// pass, and a color pass that trades its own depth write for
+/// `CompareFunction::Equal` against the depth the prepass already wrote.
+struct DepthPrepassPipelines {
+    prepass: wgpu::RenderPipeline,
+    color_equal: wgpu::RenderPipeline,
+}
+
 pub struct BasicEffect {
     pub render_pipeline: wgpu::RenderPipeline,
     pub camera_obj: GPUObject<crate::camera::Uniforms>,
+    pub light_obj: GPUObject<Light>,
+    meshes: MeshPool,
+    mesh: MeshHandle,
+    depth_prepass: Option<DepthPrepassPipelines>,
+    instance_buffer: wgpu::Buffer,
+    instance_count: u32,
 }
 
 impl BasicEffect {
-    pub fn new(gpu: &State, camera_obj: GPUObject<crate::camera::Uniforms>) -> Self{
+    pub fn new(gpu: &State, viewport: &impl ViewportImage, camera_obj: GPUObject<crate::camera::Uniforms>, light_obj: GPUObject<Light>, vertices: &[Vertex], indices: &[u16]) -> Self{
         let vs_module = gpu.device.create_shader_module(&wgpu::include_spirv!("shader.vert.spv"));
         let fs_module = gpu.device.create_shader_module(&wgpu::include_spirv!("shader.frag.spv"));
 
         let render_pipeline_layout =
         gpu.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&camera_obj.layout],
+            bind_group_layouts: &[&camera_obj.layout, &light_obj.layout],
             push_constant_ranges: &[],
         });
 
@@ -146,13 +257,13 @@ impl BasicEffect {
             vertex: wgpu::VertexState {
                 module: &vs_module,
                 entry_point: "main", // 1.
-                buffers: &[], // 2.
+                buffers: &[Vertex::desc(), InstanceRaw::desc()], // 2.
             },
             fragment: Some(wgpu::FragmentState { // 3.
                 module: &fs_module,
                 entry_point: "main",
                 targets: &[wgpu::ColorTargetState { // 4.
-                    format: gpu.sc_desc.format,
+                    format: viewport.format(),
                     alpha_blend: wgpu::BlendState::REPLACE,
                     color_blend: wgpu::BlendState::REPLACE,
                     write_mask: wgpu::ColorWrite::ALL,
@@ -181,17 +292,150 @@ impl BasicEffect {
                 alpha_to_coverage_enabled: false, // 4.
             },
         });
-        
+
+        let mut meshes = MeshPool::new();
+        let mesh = meshes.add_mesh(&gpu.device, vertices, indices);
+
+        let instance_buffer = gpu.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&[InstanceRaw { model: cgmath::Matrix4::identity().into() }]),
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+        });
+
         return Self{
             render_pipeline,
-            camera_obj
+            camera_obj,
+            light_obj,
+            meshes,
+            mesh,
+            depth_prepass: None,
+            instance_buffer,
+            instance_count: 1,
         }
     }
 
-    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>){
-        render_pass.set_pipeline(&self.render_pipeline); // 2.
+    /// Replaces the geometry this effect draws each frame, re-uploading
+    /// into the existing mesh slot rather than leaking a new one.
+    pub fn set_mesh(&mut self, gpu: &State, vertices: &[Vertex], indices: &[u16]){
+        self.meshes.replace_mesh(self.mesh, &gpu.device, vertices, indices);
+    }
+
+    /// Uploads a new set of per-instance transforms, so a single draw
+    /// call can render an N×N grid of objects instead of one. Recreates
+    /// the instance buffer when the instance count changes, otherwise
+    /// writes the existing one in place.
+    pub fn set_instances(&mut self, gpu: &State, instances: &[Instance]){
+        let raw: Vec<InstanceRaw> = instances.iter().map(Instance::to_raw).collect();
+
+        if raw.len() as u32 == self.instance_count {
+            gpu.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&raw));
+        } else {
+            self.instance_buffer = gpu.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Instance Buffer"),
+                contents: bytemuck::cast_slice(&raw),
+                usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            });
+            self.instance_count = raw.len() as u32;
+        }
+    }
+
+    /// Builds the depth-only prepass pipeline plus an `Equal`-compare
+    /// color pipeline, so `record` can render depth first and cut
+    /// overdraw on the color pass that follows.
+    pub fn enable_depth_prepass(&mut self, gpu: &State, viewport: &impl ViewportImage){
+        let vs_module = gpu.device.create_shader_module(&wgpu::include_spirv!("shader.vert.spv"));
+        let fs_module = gpu.device.create_shader_module(&wgpu::include_spirv!("shader.frag.spv"));
+
+        let pipeline_layout = gpu.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Depth Prepass Pipeline Layout"),
+            bind_group_layouts: &[&self.camera_obj.layout, &self.light_obj.layout],
+            push_constant_ranges: &[],
+        });
+
+        let prepass = gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Depth Prepass Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vs_module,
+                entry_point: "main",
+                buffers: &[Vertex::desc(), InstanceRaw::desc()],
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::Back,
+                polygon_mode: wgpu::PolygonMode::Fill,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+                clamp_depth: false,
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        });
+
+        let color_equal = gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Depth-Equal Color Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vs_module,
+                entry_point: "main",
+                buffers: &[Vertex::desc(), InstanceRaw::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &fs_module,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: viewport.format(),
+                    alpha_blend: wgpu::BlendState::REPLACE,
+                    color_blend: wgpu::BlendState::REPLACE,
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::Back,
+                polygon_mode: wgpu::PolygonMode::Fill,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Equal,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+                clamp_depth: false,
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        });
+
+        self.depth_prepass = Some(DepthPrepassPipelines { prepass, color_equal });
+    }
+
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a wgpu::RenderPipeline){
+        let (vertex_buffer, index_buffer, index_count) = self.meshes.get(self.mesh);
+
+        render_pass.set_pipeline(pipeline);
         render_pass.set_bind_group(self.camera_obj.binding, &self.camera_obj.bind_group, &[]); //TODO, the gpu object should know what its bind group is.
-        render_pass.draw(0..3, 0..1); // 3.
+        render_pass.set_bind_group(self.light_obj.binding, &self.light_obj.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..index_count, 0, 0..self.instance_count); // 3.
     }
 
     pub fn write_buffer(&self, gpu: &State, buffer: &wgpu::Buffer, bytes: impl bytemuck::Pod ){
@@ -202,4 +446,149 @@ impl BasicEffect {
         gpu.queue.write_buffer(&self.camera_obj.buffer, 0, bytemuck::cast_slice(&[bytes]));
     }
 
+    pub fn write_light_buffer(&self, gpu: &State, bytes: impl bytemuck::Pod ){
+        gpu.queue.write_buffer(&self.light_obj.buffer, 0, bytemuck::cast_slice(&[bytes]));
+    }
+
+}
+
+impl RenderPass for BasicEffect {
+    fn record<'a>(&'a self, encoder: &mut wgpu::CommandEncoder, targets: &PassTargets<'a>) {
+        if let Some(passes) = &self.depth_prepass {
+            let mut depth_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Depth Prepass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &targets.depth_view.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            self.render(&mut depth_pass, &passes.prepass);
+        }
+
+        let color_pipeline = self.depth_prepass.as_ref()
+            .map(|passes| &passes.color_equal)
+            .unwrap_or(&self.render_pipeline);
+        let depth_load = if self.depth_prepass.is_some() { wgpu::LoadOp::Load } else { wgpu::LoadOp::Clear(1.0) };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: targets.frame_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.1,
+                        g: 0.2,
+                        b: 0.3,
+                        a: 1.0,
+                    }),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                attachment: &targets.depth_view.view,
+                depth_ops: Some(wgpu::Operations {
+                    load: depth_load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        self.render(&mut render_pass, color_pipeline);
+    }
+}
+
+/// Visualizes the linearized depth buffer by sampling it with a
+/// comparison sampler and drawing a single fullscreen triangle (three
+/// vertices, no vertex buffer). Toggle on via
+/// `State::set_debug_depth_pass`.
+pub struct DepthEffect {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+}
+
+impl DepthEffect {
+    pub fn new(gpu: &State, viewport: &impl ViewportImage) -> Self {
+        let bind_group_layout = texture::Texture::depth_bind_group_layout(&gpu.device);
+
+        let vs_module = gpu.device.create_shader_module(&wgpu::include_spirv!("depth.vert.spv"));
+        let fs_module = gpu.device.create_shader_module(&wgpu::include_spirv!("depth.frag.spv"));
+
+        let pipeline_layout = gpu.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Depth Visualization Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = gpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Depth Visualization Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vs_module,
+                entry_point: "main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &fs_module,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: viewport.format(),
+                    alpha_blend: wgpu::BlendState::REPLACE,
+                    color_blend: wgpu::BlendState::REPLACE,
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        });
+
+        let bind_group = viewport.depth_texture().depth_bind_group(&gpu.device, &bind_group_layout);
+
+        Self { pipeline, bind_group_layout, bind_group }
+    }
+
+    /// Rebuilds the depth-texture bind group against the viewport's
+    /// current depth texture. `State::resize` calls this automatically
+    /// for the active debug depth pass, so apps don't need to call it
+    /// by hand after a resize.
+    pub fn rebuild(&mut self, gpu: &State, viewport: &impl ViewportImage) {
+        self.bind_group = viewport.depth_texture().depth_bind_group(&gpu.device, &self.bind_group_layout);
+    }
+}
+
+impl RenderPass for DepthEffect {
+    fn record<'a>(&'a self, encoder: &mut wgpu::CommandEncoder, targets: &PassTargets<'a>) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Visualization Pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                attachment: targets.frame_view,
+                resolve_target: None,
+                ops: wgpu::Operations { load: wgpu::LoadOp::Load, store: true },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
 }
\ No newline at end of file
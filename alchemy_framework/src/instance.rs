@@ -0,0 +1,100 @@
+use cgmath::{Matrix4, Quaternion, Vector3};
+
+/// A single instance's world transform, lowered to `InstanceRaw` before
+/// upload since GPU buffers can't carry `cgmath` types directly.
+pub struct Instance {
+    pub position: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+}
+
+impl Instance {
+    pub fn to_raw(&self) -> InstanceRaw {
+        let model = Matrix4::from_translation(self.position) * Matrix4::from(self.rotation);
+        InstanceRaw { model: model.into() }
+    }
+}
+
+/// The GPU-visible form of `Instance`: a single model matrix uploaded
+/// into a per-instance vertex buffer.
+#[repr(C)]
+#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    pub model: [[f32; 4]; 4],
+}
+
+impl InstanceRaw {
+    /// Instance attributes start at shader location 2, after the mesh's
+    /// `Vertex::desc()` attributes at locations 0 and 1.
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress * 2,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress * 3,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float4,
+                },
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Rotation3, SquareMatrix, Zero};
+
+    #[test]
+    fn to_raw_lowers_translation_and_rotation_into_a_single_matrix() {
+        let instance = Instance {
+            position: Vector3::new(1.0, 2.0, 3.0),
+            rotation: Quaternion::from_angle_z(cgmath::Deg(0.0)),
+        };
+
+        let expected: [[f32; 4]; 4] = Matrix4::from_translation(instance.position).into();
+        assert_eq!(instance.to_raw().model, expected);
+    }
+
+    #[test]
+    fn to_raw_identity_rotation_at_origin_is_identity_matrix() {
+        let instance = Instance {
+            position: Vector3::zero(),
+            rotation: Quaternion::from_angle_z(cgmath::Deg(0.0)),
+        };
+
+        let expected: [[f32; 4]; 4] = Matrix4::identity().into();
+        assert_eq!(instance.to_raw().model, expected);
+    }
+
+    #[test]
+    fn desc_attributes_start_after_vertex_locations_with_tight_packed_offsets() {
+        let desc = InstanceRaw::desc();
+
+        assert_eq!(desc.array_stride, std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress);
+        assert_eq!(desc.step_mode, wgpu::InputStepMode::Instance);
+        assert_eq!(desc.attributes.len(), 4);
+
+        let row_size = std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress;
+        for (i, attribute) in desc.attributes.iter().enumerate() {
+            assert_eq!(attribute.shader_location, 2 + i as u32);
+            assert_eq!(attribute.offset, row_size * i as wgpu::BufferAddress);
+            assert_eq!(attribute.format, wgpu::VertexFormat::Float4);
+        }
+    }
+}
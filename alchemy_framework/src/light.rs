@@ -0,0 +1,30 @@
+/// A single point light, bound as a uniform alongside the camera.
+///
+/// `_padding`/`_padding2` exist solely to satisfy std140's 16-byte
+/// field alignment inside the uniform buffer; they carry no data.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Light {
+    pub position: [f32; 3],
+    pub _padding: u32,
+    pub color: [f32; 3],
+    pub _padding2: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_and_field_offsets_satisfy_std140_16_byte_alignment() {
+        assert_eq!(std::mem::size_of::<Light>(), 32);
+
+        let light = Light { position: [0.0; 3], _padding: 0, color: [0.0; 3], _padding2: 0 };
+        let base = &light as *const Light as usize;
+        let position_offset = &light.position as *const [f32; 3] as usize - base;
+        let color_offset = &light.color as *const [f32; 3] as usize - base;
+
+        assert_eq!(position_offset, 0);
+        assert_eq!(color_offset, 16);
+    }
+}
@@ -0,0 +1,124 @@
+use wgpu::util::DeviceExt;
+
+/// A single mesh vertex: position plus texture coordinates.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
+
+impl Vertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float2,
+                },
+            ],
+        }
+    }
+}
+
+/// A handle into a `MeshPool`, opaque to callers.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MeshHandle(usize);
+
+struct Mesh {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+}
+
+/// Uploads meshes to GPU buffers and keeps them alive for drawing.
+pub struct MeshPool {
+    meshes: Vec<Mesh>,
+}
+
+impl MeshPool {
+    pub fn new() -> Self {
+        Self { meshes: Vec::new() }
+    }
+
+    pub fn add_mesh(&mut self, device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) -> MeshHandle {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsage::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsage::INDEX,
+        });
+
+        self.meshes.push(Mesh {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+        });
+
+        MeshHandle(self.meshes.len() - 1)
+    }
+
+    /// Re-uploads a mesh's geometry into its existing slot, dropping the
+    /// old vertex/index buffers instead of growing the pool. Used by
+    /// `BasicEffect::set_mesh` so swapping geometry repeatedly doesn't
+    /// leak GPU buffers.
+    pub fn replace_mesh(&mut self, handle: MeshHandle, device: &wgpu::Device, vertices: &[Vertex], indices: &[u16]) {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsage::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsage::INDEX,
+        });
+
+        self.meshes[handle.0] = Mesh {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+        };
+    }
+
+    pub(crate) fn get(&self, handle: MeshHandle) -> (&wgpu::Buffer, &wgpu::Buffer, u32) {
+        let mesh = &self.meshes[handle.0];
+        (&mesh.vertex_buffer, &mesh.index_buffer, mesh.index_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vertex_desc_matches_field_layout() {
+        let desc = Vertex::desc();
+
+        assert_eq!(desc.array_stride, std::mem::size_of::<Vertex>() as wgpu::BufferAddress);
+        assert_eq!(desc.step_mode, wgpu::InputStepMode::Vertex);
+        assert_eq!(desc.attributes.len(), 2);
+
+        assert_eq!(desc.attributes[0].offset, 0);
+        assert_eq!(desc.attributes[0].shader_location, 0);
+        assert_eq!(desc.attributes[0].format, wgpu::VertexFormat::Float3);
+
+        assert_eq!(desc.attributes[1].offset, std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress);
+        assert_eq!(desc.attributes[1].shader_location, 1);
+        assert_eq!(desc.attributes[1].format, wgpu::VertexFormat::Float2);
+    }
+}
@@ -0,0 +1,19 @@
+use crate::texture;
+
+/// The set of views a `RenderPass` records its work against: the frame
+/// currently being presented (or rendered to, for an offscreen target)
+/// and the depth buffer shared across passes in a frame.
+pub struct PassTargets<'a> {
+    pub frame_view: &'a wgpu::TextureView,
+    pub depth_view: &'a texture::Texture,
+}
+
+/// A single recorded phase of a frame, e.g. an opaque pass, a
+/// transparent pass, or a post-process pass. `State::render` iterates
+/// its registered passes in order and lets each one record its own
+/// work against the shared encoder and targets, or — with parallel
+/// recording enabled — its own encoder on a rayon worker thread, which
+/// is why the trait requires `Send + Sync`.
+pub trait RenderPass: Send + Sync {
+    fn record<'a>(&'a self, encoder: &mut wgpu::CommandEncoder, targets: &PassTargets<'a>);
+}
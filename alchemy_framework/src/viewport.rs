@@ -0,0 +1,150 @@
+use crate::texture;
+
+/// A frame acquired from a `ViewportImage`, borrowed for the duration of
+/// a single `State::render` call.
+pub enum AcquiredFrame<'a> {
+    SwapChain(wgpu::SwapChainFrame),
+    Texture(&'a wgpu::TextureView),
+}
+
+impl<'a> AcquiredFrame<'a> {
+    pub fn view(&self) -> &wgpu::TextureView {
+        match self {
+            AcquiredFrame::SwapChain(frame) => &frame.output.view,
+            AcquiredFrame::Texture(view) => view,
+        }
+    }
+}
+
+/// The frame view and matching depth buffer returned together by
+/// `ViewportImage::acquire`. Bundling them avoids taking a mutable
+/// borrow for the frame and a separate shared borrow for the depth
+/// texture off the same viewport within one `State::render` call.
+pub struct AcquiredTargets<'a> {
+    pub frame: AcquiredFrame<'a>,
+    pub depth_texture: &'a texture::Texture,
+}
+
+/// A render target `State::render` can draw into. `SwapChainViewport`
+/// presents to the window; `TextureViewport` renders offscreen for
+/// thumbnails, minimaps, and shadow maps.
+pub trait ViewportImage {
+    fn acquire(&mut self) -> Result<AcquiredTargets, wgpu::SwapChainError>;
+    fn size(&self) -> (u32, u32);
+    fn format(&self) -> wgpu::TextureFormat;
+    fn depth_texture(&self) -> &texture::Texture;
+}
+
+/// The window's swap chain, plus the depth buffer that matches it.
+pub struct SwapChainViewport {
+    swap_chain: wgpu::SwapChain,
+    sc_desc: wgpu::SwapChainDescriptor,
+    depth_texture: texture::Texture,
+}
+
+impl SwapChainViewport {
+    pub fn new(device: &wgpu::Device, surface: &wgpu::Surface, sc_desc: wgpu::SwapChainDescriptor) -> Self {
+        let swap_chain = device.create_swap_chain(surface, &sc_desc);
+        let depth_texture = texture::Texture::create_depth_texture(device, &sc_desc, "depth_texture");
+
+        Self { swap_chain, sc_desc, depth_texture }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, surface: &wgpu::Surface, width: u32, height: u32) {
+        self.sc_desc.width = width;
+        self.sc_desc.height = height;
+        self.swap_chain = device.create_swap_chain(surface, &self.sc_desc);
+        self.depth_texture = texture::Texture::create_depth_texture(device, &self.sc_desc, "depth_texture");
+    }
+
+    /// Recreates the swap chain with a new present mode. The depth
+    /// buffer is untouched since its size doesn't depend on present mode.
+    pub fn set_present_mode(&mut self, device: &wgpu::Device, surface: &wgpu::Surface, present_mode: wgpu::PresentMode) {
+        self.sc_desc.present_mode = present_mode;
+        self.swap_chain = device.create_swap_chain(surface, &self.sc_desc);
+    }
+
+    /// The present mode the swap chain is currently configured with.
+    /// This is the single source of truth; `State` keeps no copy of it.
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.sc_desc.present_mode
+    }
+}
+
+impl ViewportImage for SwapChainViewport {
+    fn acquire(&mut self) -> Result<AcquiredTargets, wgpu::SwapChainError> {
+        let frame = AcquiredFrame::SwapChain(self.swap_chain.get_current_frame()?);
+        Ok(AcquiredTargets { frame, depth_texture: &self.depth_texture })
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.sc_desc.width, self.sc_desc.height)
+    }
+
+    fn format(&self) -> wgpu::TextureFormat {
+        self.sc_desc.format
+    }
+
+    fn depth_texture(&self) -> &texture::Texture {
+        &self.depth_texture
+    }
+}
+
+/// An owned offscreen render target with its own matching depth buffer,
+/// for rendering to a texture instead of the window.
+pub struct TextureViewport {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    depth_texture: texture::Texture,
+    size: (u32, u32),
+    format: wgpu::TextureFormat,
+}
+
+impl TextureViewport {
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Viewport Texture"),
+            size: wgpu::Extent3d { width, height, depth: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::TEXTURE_BINDING,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sc_desc = wgpu::SwapChainDescriptor {
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+        };
+        let depth_texture = texture::Texture::create_depth_texture(device, &sc_desc, "offscreen_depth_texture");
+
+        Self { texture, view, depth_texture, size: (width, height), format }
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+}
+
+impl ViewportImage for TextureViewport {
+    fn acquire(&mut self) -> Result<AcquiredTargets, wgpu::SwapChainError> {
+        let frame = AcquiredFrame::Texture(&self.view);
+        Ok(AcquiredTargets { frame, depth_texture: &self.depth_texture })
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    fn depth_texture(&self) -> &texture::Texture {
+        &self.depth_texture
+    }
+}